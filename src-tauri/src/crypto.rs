@@ -0,0 +1,240 @@
+//! Argon2id key derivation + XChaCha20-Poly1305 sealing for data we persist via
+//! `tauri_plugin_store`, e.g. the Kimai API token in `credentials.json`.
+
+use std::sync::Mutex;
+
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    XChaCha20Poly1305, XNonce,
+};
+use rand::RngCore;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const KEY_LEN: usize = 32;
+
+/// Argon2id cost parameters. Stored alongside the ciphertext (in plain numeric form,
+/// they aren't secret) so a later tune of the defaults doesn't break decrypting
+/// data sealed under the old ones.
+#[derive(Debug, Clone, Copy)]
+pub struct Argon2Params {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for Argon2Params {
+    // OWASP's current minimum recommendation for Argon2id.
+    fn default() -> Self {
+        Self {
+            memory_kib: 19_456,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+/// Ciphertext plus everything needed to decrypt it again: the per-install salt, the
+/// per-message nonce, and the Argon2 cost parameters it was sealed under. The salt,
+/// nonce, and ciphertext are base64-encoded so they can be stored as plain string
+/// fields; the cost parameters are plain numbers.
+pub struct Sealed {
+    pub salt_b64: String,
+    pub nonce_b64: String,
+    pub ciphertext_b64: String,
+    pub params: Argon2Params,
+}
+
+#[derive(Debug, Clone, thiserror::Error, serde::Serialize)]
+#[serde(tag = "kind", content = "message")]
+pub enum CryptoError {
+    #[error("failed to derive key from passphrase: {0}")]
+    KeyDerivation(String),
+    #[error("stored credential data is malformed: {0}")]
+    Malformed(String),
+    #[error("incorrect passphrase or corrupted credential data")]
+    Decrypt,
+}
+
+fn build_argon2(params: &Argon2Params) -> Result<Argon2<'static>, CryptoError> {
+    let argon2_params = argon2::Params::new(params.memory_kib, params.iterations, params.parallelism, Some(KEY_LEN))
+        .map_err(|e| CryptoError::KeyDerivation(e.to_string()))?;
+    Ok(Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, argon2_params))
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], params: &Argon2Params) -> Result<[u8; KEY_LEN], CryptoError> {
+    let mut key = [0u8; KEY_LEN];
+    build_argon2(params)?
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| CryptoError::KeyDerivation(e.to_string()))?;
+    Ok(key)
+}
+
+/// `nonce_bytes` comes straight off disk, so unlike `seal`'s freshly-generated nonce
+/// it can't be trusted to be exactly `NONCE_LEN` bytes; `XNonce::from_slice` panics
+/// on a length mismatch, so validate first.
+fn to_nonce(nonce_bytes: &[u8]) -> Result<XNonce, CryptoError> {
+    if nonce_bytes.len() != NONCE_LEN {
+        return Err(CryptoError::Malformed(format!(
+            "expected a {NONCE_LEN}-byte nonce, got {}",
+            nonce_bytes.len()
+        )));
+    }
+    Ok(*XNonce::from_slice(nonce_bytes))
+}
+
+/// Derives a key from `passphrase` with a fresh random salt and encrypts `plaintext`.
+pub fn seal(passphrase: &str, plaintext: &[u8], params: Argon2Params) -> Result<Sealed, CryptoError> {
+    let mut salt = [0u8; SALT_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    let key = derive_key(passphrase, &salt, &params)?;
+
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce_bytes);
+    let nonce = XNonce::from_slice(&nonce_bytes);
+
+    let cipher = XChaCha20Poly1305::new(key.as_slice().into());
+    let ciphertext = cipher
+        .encrypt(nonce, plaintext)
+        .map_err(|_| CryptoError::Decrypt)?;
+
+    Ok(Sealed {
+        salt_b64: STANDARD.encode(salt),
+        nonce_b64: STANDARD.encode(nonce_bytes),
+        ciphertext_b64: STANDARD.encode(ciphertext),
+        params,
+    })
+}
+
+/// Re-derives the key from the stored salt and params and decrypts `sealed.ciphertext_b64`.
+pub fn unseal(passphrase: &str, sealed: &Sealed) -> Result<Vec<u8>, CryptoError> {
+    let salt = STANDARD
+        .decode(&sealed.salt_b64)
+        .map_err(|e| CryptoError::Malformed(e.to_string()))?;
+    let key = derive_key(passphrase, &salt, &sealed.params)?;
+    decrypt_with_key(sealed, &key)
+}
+
+/// Caches the most recently derived Argon2 key, keyed by the passphrase and salt it
+/// was derived from, so callers that unseal the same credentials repeatedly (e.g.
+/// `kimai_request` on every API call) don't pay the KDF's deliberately-expensive cost
+/// each time; only re-derived when the passphrase or salt actually change.
+#[derive(Default)]
+pub struct DerivedKeyCache(Mutex<Option<(String, String, [u8; KEY_LEN])>>);
+
+impl DerivedKeyCache {
+    /// Drops the cached key. Call when the underlying credentials change, e.g. from
+    /// `clear_credentials`, so a stale key can't outlive the data it was derived from.
+    pub fn clear(&self) {
+        *self.0.lock().unwrap() = None;
+    }
+}
+
+fn derive_key_cached(
+    cache: &DerivedKeyCache,
+    passphrase: &str,
+    salt: &[u8],
+    salt_b64: &str,
+    params: &Argon2Params,
+) -> Result<[u8; KEY_LEN], CryptoError> {
+    let mut cached = cache.0.lock().unwrap();
+    if let Some((cached_passphrase, cached_salt_b64, key)) = cached.as_ref() {
+        if cached_passphrase == passphrase && cached_salt_b64 == salt_b64 {
+            return Ok(*key);
+        }
+    }
+
+    let key = derive_key(passphrase, salt, params)?;
+    *cached = Some((passphrase.to_string(), salt_b64.to_string(), key));
+    Ok(key)
+}
+
+/// Same as [`unseal`], but re-uses the previously derived key from `cache` when
+/// `passphrase` and the stored salt haven't changed instead of re-running Argon2id.
+pub fn unseal_cached(passphrase: &str, sealed: &Sealed, cache: &DerivedKeyCache) -> Result<Vec<u8>, CryptoError> {
+    let salt = STANDARD
+        .decode(&sealed.salt_b64)
+        .map_err(|e| CryptoError::Malformed(e.to_string()))?;
+    let key = derive_key_cached(cache, passphrase, &salt, &sealed.salt_b64, &sealed.params)?;
+    decrypt_with_key(sealed, &key)
+}
+
+fn decrypt_with_key(sealed: &Sealed, key: &[u8; KEY_LEN]) -> Result<Vec<u8>, CryptoError> {
+    let nonce_bytes = STANDARD
+        .decode(&sealed.nonce_b64)
+        .map_err(|e| CryptoError::Malformed(e.to_string()))?;
+    let ciphertext = STANDARD
+        .decode(&sealed.ciphertext_b64)
+        .map_err(|e| CryptoError::Malformed(e.to_string()))?;
+
+    let nonce = to_nonce(&nonce_bytes)?;
+    let cipher = XChaCha20Poly1305::new(key.as_slice().into());
+
+    cipher
+        .decrypt(&nonce, ciphertext.as_slice())
+        .map_err(|_| CryptoError::Decrypt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_with_the_correct_passphrase() {
+        let sealed = seal("correct horse battery staple", b"kimai-api-token", Argon2Params::default()).unwrap();
+        let plaintext = unseal("correct horse battery staple", &sealed).unwrap();
+        assert_eq!(plaintext, b"kimai-api-token");
+    }
+
+    #[test]
+    fn rejects_the_wrong_passphrase() {
+        let sealed = seal("correct horse battery staple", b"kimai-api-token", Argon2Params::default()).unwrap();
+        let err = unseal("wrong passphrase", &sealed).unwrap_err();
+        assert!(matches!(err, CryptoError::Decrypt));
+    }
+
+    #[test]
+    fn rejects_a_truncated_nonce_without_panicking() {
+        let mut sealed = seal("correct horse battery staple", b"kimai-api-token", Argon2Params::default()).unwrap();
+        sealed.nonce_b64 = STANDARD.encode([0u8; NONCE_LEN - 1]);
+        let err = unseal("correct horse battery staple", &sealed).unwrap_err();
+        assert!(matches!(err, CryptoError::Malformed(_)));
+    }
+
+    #[test]
+    fn unseal_cached_round_trips_just_like_unseal() {
+        let sealed = seal("correct horse battery staple", b"kimai-api-token", Argon2Params::default()).unwrap();
+        let cache = DerivedKeyCache::default();
+        let plaintext = unseal_cached("correct horse battery staple", &sealed, &cache).unwrap();
+        assert_eq!(plaintext, b"kimai-api-token");
+    }
+
+    #[test]
+    fn unseal_cached_reuses_the_key_for_the_same_passphrase_and_salt() {
+        let mut sealed = seal("correct horse battery staple", b"kimai-api-token", Argon2Params::default()).unwrap();
+        let cache = DerivedKeyCache::default();
+
+        unseal_cached("correct horse battery staple", &sealed, &cache).unwrap();
+        let cached_key = cache.0.lock().unwrap().clone().unwrap();
+
+        // Argon2 rejects zero memory, so the second call only succeeds if it skips re-deriving.
+        sealed.params.memory_kib = 0;
+        let plaintext = unseal_cached("correct horse battery staple", &sealed, &cache).unwrap();
+        let still_cached_key = cache.0.lock().unwrap().clone().unwrap();
+
+        assert_eq!(plaintext, b"kimai-api-token");
+        assert_eq!(cached_key, still_cached_key, "the second call should reuse the cached key, not re-derive it");
+    }
+
+    #[test]
+    fn unseal_cached_rederives_when_the_passphrase_changes() {
+        let sealed = seal("correct horse battery staple", b"kimai-api-token", Argon2Params::default()).unwrap();
+        let cache = DerivedKeyCache::default();
+
+        let _ = unseal_cached("wrong passphrase", &sealed, &cache);
+        let plaintext = unseal_cached("correct horse battery staple", &sealed, &cache).unwrap();
+        assert_eq!(plaintext, b"kimai-api-token");
+    }
+}
@@ -0,0 +1,213 @@
+//! Polls OS "time since last input" so a running timer can be auto-paused when the
+//! user has walked away, instead of silently over-reporting tracked time.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+pub const DEFAULT_IDLE_THRESHOLD_SECS: u64 = 300;
+
+/// Shared, lock-free idle-detection config read by the background poll thread and
+/// written by the `set_idle_threshold`/`set_idle_detection` commands.
+pub struct IdleState {
+    pub threshold_secs: AtomicU64,
+    pub enabled: AtomicBool,
+}
+
+impl Default for IdleState {
+    fn default() -> Self {
+        Self {
+            threshold_secs: AtomicU64::new(DEFAULT_IDLE_THRESHOLD_SECS),
+            enabled: AtomicBool::new(true),
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+struct IdleDetectedPayload {
+    idle_seconds: u64,
+}
+
+/// What, if anything, should be emitted this tick given the previous and current
+/// idle state. Pulled out of `spawn_idle_monitor`'s loop so the edge-detection
+/// logic can be unit-tested without a real poll thread or `AppHandle`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transition {
+    None,
+    BecameIdle,
+    Resumed,
+}
+
+fn next_state(was_idle: bool, idle_secs: u64, threshold_secs: u64) -> (bool, Transition) {
+    let is_idle = idle_secs >= threshold_secs;
+    let transition = match (was_idle, is_idle) {
+        (false, true) => Transition::BecameIdle,
+        (true, false) => Transition::Resumed,
+        _ => Transition::None,
+    };
+    (is_idle, transition)
+}
+
+/// Spawns the background thread that polls idle time and emits `idle-detected` /
+/// `idle-resumed` events on state transitions. Call once from `setup`.
+pub fn spawn_idle_monitor(app: AppHandle) {
+    thread::spawn(move || {
+        let state = app.state::<IdleState>();
+        let mut was_idle = false;
+
+        loop {
+            thread::sleep(POLL_INTERVAL);
+
+            if !state.enabled.load(Ordering::Relaxed) {
+                was_idle = false;
+                continue;
+            }
+
+            let idle_secs = idle_seconds();
+            let threshold = state.threshold_secs.load(Ordering::Relaxed);
+            let (is_idle, transition) = next_state(was_idle, idle_secs, threshold);
+
+            match transition {
+                Transition::BecameIdle => {
+                    let _ = app.emit("idle-detected", IdleDetectedPayload { idle_seconds: idle_secs });
+                }
+                Transition::Resumed => {
+                    let _ = app.emit("idle-resumed", ());
+                }
+                Transition::None => {}
+            }
+            was_idle = is_idle;
+        }
+    });
+}
+
+/// Seconds since the last keyboard/mouse input was observed by the OS.
+#[cfg(target_os = "windows")]
+fn idle_seconds() -> u64 {
+    use windows::Win32::System::SystemInformation::GetTickCount;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+
+    let mut info = LASTINPUTINFO {
+        cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+        dwTime: 0,
+    };
+
+    unsafe {
+        if GetLastInputInfo(&mut info).as_bool() {
+            GetTickCount().saturating_sub(info.dwTime) as u64 / 1000
+        } else {
+            0
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod macos_ffi {
+    // `core-graphics` has no binding for this function, so we declare it ourselves
+    // as documented in <CoreGraphics/CGEventSource.h>.
+    #[repr(C)]
+    pub struct CGEventSourceStateID(pub i32);
+    pub const K_CG_EVENT_SOURCE_STATE_COMBINED_SESSION_STATE: CGEventSourceStateID = CGEventSourceStateID(0);
+
+    #[repr(C)]
+    pub struct CGEventType(pub u32);
+    pub const K_CG_ANY_INPUT_EVENT_TYPE: CGEventType = CGEventType(!0);
+
+    extern "C" {
+        pub fn CGEventSourceSecondsSinceLastEventType(
+            state_id: CGEventSourceStateID,
+            event_type: CGEventType,
+        ) -> f64;
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn idle_seconds() -> u64 {
+    use macos_ffi::{
+        CGEventSourceSecondsSinceLastEventType, K_CG_ANY_INPUT_EVENT_TYPE,
+        K_CG_EVENT_SOURCE_STATE_COMBINED_SESSION_STATE,
+    };
+
+    let seconds = unsafe {
+        CGEventSourceSecondsSinceLastEventType(
+            K_CG_EVENT_SOURCE_STATE_COMBINED_SESSION_STATE,
+            K_CG_ANY_INPUT_EVENT_TYPE,
+        )
+    };
+    seconds as u64
+}
+
+#[cfg(target_os = "linux")]
+thread_local! {
+    // `idle_seconds` is only ever called from the single dedicated poll thread
+    // spawned by `spawn_idle_monitor`, so a thread-local raw pointer is enough to
+    // keep the connection alive for the thread's lifetime without needing Send/Sync.
+    static DISPLAY: std::cell::Cell<*mut x11::xlib::Display> = std::cell::Cell::new(std::ptr::null_mut());
+}
+
+#[cfg(target_os = "linux")]
+fn idle_seconds() -> u64 {
+    use std::ptr;
+    use x11::xlib::{XDefaultRootWindow, XFree, XOpenDisplay};
+    use x11::xss::{XScreenSaverAllocInfo, XScreenSaverQueryInfo};
+
+    DISPLAY.with(|cell| {
+        let mut display = cell.get();
+        if display.is_null() {
+            display = unsafe { XOpenDisplay(ptr::null()) };
+            cell.set(display);
+        }
+        if display.is_null() {
+            return 0;
+        }
+
+        unsafe {
+            let root = XDefaultRootWindow(display);
+            let info = XScreenSaverAllocInfo();
+            let idle_ms = if XScreenSaverQueryInfo(display, root, info) != 0 {
+                (*info).idle
+            } else {
+                0
+            };
+            XFree(info as *mut _);
+            idle_ms / 1000
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stays_not_idle_below_threshold() {
+        let (is_idle, transition) = next_state(false, 10, 300);
+        assert!(!is_idle);
+        assert_eq!(transition, Transition::None);
+    }
+
+    #[test]
+    fn becomes_idle_once_threshold_is_crossed() {
+        let (is_idle, transition) = next_state(false, 300, 300);
+        assert!(is_idle);
+        assert_eq!(transition, Transition::BecameIdle);
+    }
+
+    #[test]
+    fn stays_idle_while_still_above_threshold() {
+        let (is_idle, transition) = next_state(true, 600, 300);
+        assert!(is_idle);
+        assert_eq!(transition, Transition::None);
+    }
+
+    #[test]
+    fn resumes_once_input_is_seen_again() {
+        let (is_idle, transition) = next_state(true, 0, 300);
+        assert!(!is_idle);
+        assert_eq!(transition, Transition::Resumed);
+    }
+}
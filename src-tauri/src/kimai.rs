@@ -0,0 +1,161 @@
+//! HTTP bridge for reaching Kimai instances the webview's own `fetch` can't: those
+//! behind a corporate proxy or serving a private root CA.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Serialize)]
+pub struct KimaiResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+#[derive(Clone, PartialEq, Eq)]
+struct ClientKey {
+    proxy_url: Option<String>,
+    ca_cert_path: Option<String>,
+}
+
+/// Caches the `reqwest::Client` built from the current proxy/CA settings so a
+/// fresh TLS/connection pool isn't paid for on every single `kimai_request` call;
+/// only rebuilt when those settings actually change.
+#[derive(Default)]
+pub struct KimaiClientState(Mutex<Option<(ClientKey, reqwest::Client)>>);
+
+fn build_client(proxy_url: Option<&str>, ca_cert_path: Option<&str>) -> Result<reqwest::Client, String> {
+    #[cfg(test)]
+    BUILD_COUNT.with(|count| count.set(count.get() + 1));
+
+    let mut builder = reqwest::ClientBuilder::new();
+
+    if let Some(proxy_url) = proxy_url {
+        let proxy = reqwest::Proxy::all(proxy_url).map_err(|e| e.to_string())?;
+        builder = builder.proxy(proxy);
+    }
+
+    if let Some(ca_cert_path) = ca_cert_path {
+        let pem = std::fs::read(ca_cert_path).map_err(|e| format!("failed to read CA certificate: {e}"))?;
+        let cert = reqwest::Certificate::from_pem(&pem).map_err(|e| e.to_string())?;
+        builder = builder.add_root_certificate(cert);
+    }
+
+    builder.build().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+thread_local! {
+    // Per-thread so tests running in parallel don't count each other's builds.
+    static BUILD_COUNT: std::cell::Cell<usize> = std::cell::Cell::new(0);
+}
+
+fn get_or_build_client(
+    state: &KimaiClientState,
+    proxy_url: Option<&str>,
+    ca_cert_path: Option<&str>,
+) -> Result<reqwest::Client, String> {
+    let key = ClientKey {
+        proxy_url: proxy_url.map(str::to_string),
+        ca_cert_path: ca_cert_path.map(str::to_string),
+    };
+
+    let mut cached = state.0.lock().unwrap();
+    if let Some((cached_key, client)) = cached.as_ref() {
+        if *cached_key == key {
+            return Ok(client.clone());
+        }
+    }
+
+    let client = build_client(proxy_url, ca_cert_path)?;
+    *cached = Some((key, client.clone()));
+    Ok(client)
+}
+
+/// Issues `method path` against `server_url` with the Kimai API token attached,
+/// routed through the configured proxy/CA if any.
+pub async fn send_request(
+    state: &KimaiClientState,
+    server_url: &str,
+    api_token: &str,
+    method: &str,
+    path: &str,
+    body: Option<Value>,
+    headers: Option<HashMap<String, String>>,
+    proxy_url: Option<&str>,
+    ca_cert_path: Option<&str>,
+) -> Result<KimaiResponse, String> {
+    let client = get_or_build_client(state, proxy_url, ca_cert_path)?;
+    let method: reqwest::Method = method.parse().map_err(|_| format!("invalid HTTP method: {method}"))?;
+    let url = format!("{}/{}", server_url.trim_end_matches('/'), path.trim_start_matches('/'));
+
+    let mut request = client.request(method, url);
+
+    if let Some(headers) = headers {
+        for (name, value) in headers {
+            // `.header()` appends rather than replaces, so a caller-supplied
+            // Authorization would otherwise ride along next to the stored token
+            // instead of being overridden by it.
+            if name.eq_ignore_ascii_case("authorization") {
+                continue;
+            }
+            request = request.header(name, value);
+        }
+    }
+
+    request = request.header("Authorization", format!("Bearer {api_token}"));
+
+    if let Some(body) = body {
+        request = request.json(&body);
+    }
+
+    let response = request.send().await.map_err(|e| e.to_string())?;
+    let status = response.status().as_u16();
+    let body = response.text().await.map_err(|e| e.to_string())?;
+
+    Ok(KimaiResponse { status, body })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn client_keys_with_the_same_settings_are_equal() {
+        let a = ClientKey { proxy_url: Some("http://proxy:8080".into()), ca_cert_path: None };
+        let b = ClientKey { proxy_url: Some("http://proxy:8080".into()), ca_cert_path: None };
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn client_keys_with_different_settings_are_not_equal() {
+        let a = ClientKey { proxy_url: Some("http://proxy:8080".into()), ca_cert_path: None };
+        let b = ClientKey { proxy_url: None, ca_cert_path: None };
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn reuses_the_cached_client_when_settings_are_unchanged() {
+        let state = KimaiClientState::default();
+        let before = BUILD_COUNT.with(|count| count.get());
+
+        get_or_build_client(&state, Some("http://proxy:8080"), None).unwrap();
+        get_or_build_client(&state, Some("http://proxy:8080"), None).unwrap();
+
+        let builds = BUILD_COUNT.with(|count| count.get()) - before;
+        assert_eq!(builds, 1, "second call with identical settings should hit the cache");
+    }
+
+    #[test]
+    fn rebuilds_when_settings_change() {
+        let state = KimaiClientState::default();
+        let before = BUILD_COUNT.with(|count| count.get());
+
+        get_or_build_client(&state, Some("http://proxy:8080"), None).unwrap();
+        get_or_build_client(&state, Some("http://other-proxy:8080"), None).unwrap();
+
+        let builds = BUILD_COUNT.with(|count| count.get()) - before;
+        assert_eq!(builds, 2, "a changed setting should force a rebuild");
+    }
+}
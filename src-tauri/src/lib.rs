@@ -1,10 +1,31 @@
 use serde_json::Value;
 use tauri::{
-    menu::{Menu, MenuItem},
+    menu::{CheckMenuItem, Menu, MenuItem},
     tray::{TrayIconBuilder, TrayIconEvent},
     Manager,
 };
+use tauri::Emitter;
+use tauri_plugin_autostart::ManagerExt;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
 use tauri_plugin_notification::NotificationExt;
+use tauri_plugin_updater::UpdaterExt;
+
+mod crypto;
+mod idle;
+mod kimai;
+mod updater;
+
+use idle::IdleState;
+use std::sync::atomic::{AtomicBool, Ordering};
+use updater::UpdateState;
+
+/// Tracks whether the active time entry is running, so the global-hotkey handler's
+/// notification can say "started"/"stopped" instead of a generic "toggled". Flipped
+/// by the hotkey itself; kept in sync with UI-driven start/stop via `set_timer_running`.
+#[derive(Default)]
+struct TimerState {
+    running: AtomicBool,
+}
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
@@ -13,35 +34,275 @@ fn greet(name: &str) -> String {
 }
 
 #[tauri::command]
-async fn save_credentials(app: tauri::AppHandle, server_url: String, api_token: String) -> Result<(), String> {
-    let store = tauri_plugin_store::StoreBuilder::new(&app, "credentials.json").build().map_err(|e| e.to_string())?;
+async fn save_credentials(
+    app: tauri::AppHandle,
+    server_url: String,
+    api_token: String,
+    passphrase: String,
+    argon2_memory_kib: Option<u32>,
+    argon2_iterations: Option<u32>,
+) -> Result<(), crypto::CryptoError> {
+    let store = tauri_plugin_store::StoreBuilder::new(&app, "credentials.json")
+        .build()
+        .map_err(|e| crypto::CryptoError::Malformed(e.to_string()))?;
+
+    let defaults = crypto::Argon2Params::default();
+    let params = crypto::Argon2Params {
+        memory_kib: argon2_memory_kib.unwrap_or(defaults.memory_kib),
+        iterations: argon2_iterations.unwrap_or(defaults.iterations),
+        parallelism: defaults.parallelism,
+    };
+    let sealed = crypto::seal(&passphrase, api_token.as_bytes(), params)?;
+
     store.set("server_url", Value::String(server_url));
-    store.set("api_token", Value::String(api_token));
-    store.save().map_err(|e| e.to_string())?;
+    store.set("salt", Value::String(sealed.salt_b64));
+    store.set("nonce", Value::String(sealed.nonce_b64));
+    store.set("ciphertext", Value::String(sealed.ciphertext_b64));
+    store.set("argon2_memory_kib", Value::Number(sealed.params.memory_kib.into()));
+    store.set("argon2_iterations", Value::Number(sealed.params.iterations.into()));
+    store.set("argon2_parallelism", Value::Number(sealed.params.parallelism.into()));
+    store.save().map_err(|e| crypto::CryptoError::Malformed(e.to_string()))?;
     Ok(())
 }
 
-#[tauri::command]
-async fn load_credentials(app: tauri::AppHandle) -> Result<Option<(String, String)>, String> {
-    let store = tauri_plugin_store::StoreBuilder::new(&app, "credentials.json").build().map_err(|e| e.to_string())?;
-    
+/// Reads `credentials.json` and decrypts the API token with `passphrase`. Shared by
+/// the `load_credentials` command and `kimai_request`, which both need the
+/// plaintext token but must never persist or pass it back over IPC unprompted.
+///
+/// Uses the app's `DerivedKeyCache` rather than `crypto::unseal` directly: `kimai_request`
+/// calls this on every single API request, and re-running Argon2id synchronously on the
+/// async runtime each time would add real per-call latency and could stall other tasks.
+fn read_and_unseal_credentials(
+    app: &tauri::AppHandle,
+    passphrase: &str,
+) -> Result<Option<(String, String)>, crypto::CryptoError> {
+    let store = tauri_plugin_store::StoreBuilder::new(app, "credentials.json")
+        .build()
+        .map_err(|e| crypto::CryptoError::Malformed(e.to_string()))?;
+
     let server_url = store.get("server_url").and_then(|v| v.as_str().map(|s| s.to_string()));
-    let api_token = store.get("api_token").and_then(|v| v.as_str().map(|s| s.to_string()));
-    
-    match (server_url, api_token) {
-        (Some(url), Some(token)) => Ok(Some((url, token))),
+    let salt_b64 = store.get("salt").and_then(|v| v.as_str().map(|s| s.to_string()));
+    let nonce_b64 = store.get("nonce").and_then(|v| v.as_str().map(|s| s.to_string()));
+    let ciphertext_b64 = store.get("ciphertext").and_then(|v| v.as_str().map(|s| s.to_string()));
+    let defaults = crypto::Argon2Params::default();
+    let memory_kib = store.get("argon2_memory_kib").and_then(|v| v.as_u64()).map(|n| n as u32).unwrap_or(defaults.memory_kib);
+    let iterations = store.get("argon2_iterations").and_then(|v| v.as_u64()).map(|n| n as u32).unwrap_or(defaults.iterations);
+    let parallelism = store.get("argon2_parallelism").and_then(|v| v.as_u64()).map(|n| n as u32).unwrap_or(defaults.parallelism);
+
+    match (server_url, salt_b64, nonce_b64, ciphertext_b64) {
+        (Some(url), Some(salt_b64), Some(nonce_b64), Some(ciphertext_b64)) => {
+            let sealed = crypto::Sealed {
+                salt_b64,
+                nonce_b64,
+                ciphertext_b64,
+                params: crypto::Argon2Params { memory_kib, iterations, parallelism },
+            };
+            let cache = app.state::<crypto::DerivedKeyCache>();
+            let token_bytes = crypto::unseal_cached(passphrase, &sealed, &cache)?;
+            let token = String::from_utf8(token_bytes).map_err(|e| crypto::CryptoError::Malformed(e.to_string()))?;
+            Ok(Some((url, token)))
+        }
         _ => Ok(None),
     }
 }
 
+#[tauri::command]
+async fn load_credentials(
+    app: tauri::AppHandle,
+    passphrase: String,
+) -> Result<Option<(String, String)>, crypto::CryptoError> {
+    read_and_unseal_credentials(&app, &passphrase)
+}
+
 #[tauri::command]
 async fn clear_credentials(app: tauri::AppHandle) -> Result<(), String> {
     let store = tauri_plugin_store::StoreBuilder::new(&app, "credentials.json").build().map_err(|e| e.to_string())?;
     store.clear();
     store.save().map_err(|e| e.to_string())?;
+    app.state::<crypto::DerivedKeyCache>().clear();
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_autostart(app: tauri::AppHandle, enable: bool) -> Result<(), String> {
+    let autostart_manager = app.autolaunch();
+    if enable {
+        autostart_manager.enable().map_err(|e| e.to_string())?;
+    } else {
+        autostart_manager.disable().map_err(|e| e.to_string())?;
+    }
+
+    let store = tauri_plugin_store::StoreBuilder::new(&app, "settings.json").build().map_err(|e| e.to_string())?;
+    store.set("autostart", Value::Bool(enable));
+    store.save().map_err(|e| e.to_string())?;
+
+    if let Some(tray) = app.tray_by_id("main") {
+        if let Some(menu) = tray.menu() {
+            if let Some(item) = menu.get("autostart") {
+                if let Some(check_item) = item.as_check_menuitem() {
+                    check_item.set_checked(enable).map_err(|e| e.to_string())?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+fn get_autostart(app: tauri::AppHandle) -> Result<bool, String> {
+    app.autolaunch().is_enabled().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn set_idle_threshold(app: tauri::AppHandle, seconds: u64) -> Result<(), String> {
+    app.state::<IdleState>().threshold_secs.store(seconds, Ordering::Relaxed);
+
+    let store = tauri_plugin_store::StoreBuilder::new(&app, "settings.json").build().map_err(|e| e.to_string())?;
+    store.set("idle_threshold_secs", Value::Number(seconds.into()));
+    store.save().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_idle_detection(app: tauri::AppHandle, enabled: bool) -> Result<(), String> {
+    app.state::<IdleState>().enabled.store(enabled, Ordering::Relaxed);
+
+    let store = tauri_plugin_store::StoreBuilder::new(&app, "settings.json").build().map_err(|e| e.to_string())?;
+    store.set("idle_detection_enabled", Value::Bool(enabled));
+    store.save().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Parses `accelerator` and re-registers it as the sole global shortcut, replacing
+/// whatever was previously bound. Shared by `set_toggle_hotkey` and setup's restore.
+fn register_toggle_hotkey(app: &tauri::AppHandle, accelerator: &str) -> Result<(), String> {
+    let shortcut: Shortcut = accelerator.parse().map_err(|_| format!("invalid accelerator: {accelerator}"))?;
+    let manager = app.global_shortcut();
+    manager.unregister_all().map_err(|e| e.to_string())?;
+    manager.register(shortcut).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_toggle_hotkey(app: tauri::AppHandle, accelerator: String) -> Result<(), String> {
+    register_toggle_hotkey(&app, &accelerator)?;
+
+    let store = tauri_plugin_store::StoreBuilder::new(&app, "settings.json").build().map_err(|e| e.to_string())?;
+    store.set("toggle_hotkey", Value::String(accelerator));
+    store.save().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+async fn clear_toggle_hotkey(app: tauri::AppHandle) -> Result<(), String> {
+    app.global_shortcut().unregister_all().map_err(|e| e.to_string())?;
+
+    let store = tauri_plugin_store::StoreBuilder::new(&app, "settings.json").build().map_err(|e| e.to_string())?;
+    store.delete("toggle_hotkey");
+    store.save().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Lets the frontend keep the backend's start/stop notion in sync when the timer is
+/// toggled from the UI rather than the hotkey, so the next hotkey press still
+/// announces the correct direction.
+#[tauri::command]
+fn set_timer_running(app: tauri::AppHandle, running: bool) {
+    app.state::<TimerState>().running.store(running, Ordering::Relaxed);
+}
+
+#[tauri::command]
+async fn kimai_request(
+    app: tauri::AppHandle,
+    method: String,
+    path: String,
+    body: Option<Value>,
+    headers: Option<std::collections::HashMap<String, String>>,
+    passphrase: String,
+) -> Result<kimai::KimaiResponse, String> {
+    let (server_url, api_token) = read_and_unseal_credentials(&app, &passphrase)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "no Kimai server configured".to_string())?;
+
+    let settings = tauri_plugin_store::StoreBuilder::new(&app, "settings.json").build().map_err(|e| e.to_string())?;
+    let proxy_url = settings.get("proxy_url").and_then(|v| v.as_str().map(|s| s.to_string()));
+    let ca_cert_path = settings.get("ca_cert_path").and_then(|v| v.as_str().map(|s| s.to_string()));
+
+    kimai::send_request(
+        &app.state::<kimai::KimaiClientState>(),
+        &server_url,
+        &api_token,
+        &method,
+        &path,
+        body,
+        headers,
+        proxy_url.as_deref(),
+        ca_cert_path.as_deref(),
+    )
+    .await
+}
+
+#[tauri::command]
+async fn set_proxy(app: tauri::AppHandle, url: Option<String>) -> Result<(), String> {
+    let store = tauri_plugin_store::StoreBuilder::new(&app, "settings.json").build().map_err(|e| e.to_string())?;
+    match url {
+        Some(url) => store.set("proxy_url", Value::String(url)),
+        None => store.delete("proxy_url"),
+    };
+    store.save().map_err(|e| e.to_string())?;
     Ok(())
 }
 
+#[tauri::command]
+async fn set_custom_ca(app: tauri::AppHandle, path: Option<String>) -> Result<(), String> {
+    let store = tauri_plugin_store::StoreBuilder::new(&app, "settings.json").build().map_err(|e| e.to_string())?;
+    match path {
+        Some(path) => store.set("ca_cert_path", Value::String(path)),
+        None => store.delete("ca_cert_path"),
+    };
+    store.save().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_auto_check_updates(app: tauri::AppHandle, enabled: bool) -> Result<(), String> {
+    let store = tauri_plugin_store::StoreBuilder::new(&app, "settings.json").build().map_err(|e| e.to_string())?;
+    store.set("auto_check_updates", Value::Bool(enabled));
+    store.save().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+async fn check_for_updates(app: tauri::AppHandle) -> Result<bool, String> {
+    let updater = app.updater().map_err(|e| e.to_string())?;
+    match updater.check().await.map_err(|e| e.to_string())? {
+        Some(update) => {
+            *app.state::<UpdateState>().0.lock().unwrap() = Some(update);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+#[tauri::command]
+async fn install_update(app: tauri::AppHandle) -> Result<(), String> {
+    let update = app
+        .state::<UpdateState>()
+        .0
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or_else(|| "no update has been checked for yet".to_string())?;
+
+    update
+        .download_and_install(|_chunk_len, _content_len| {}, || {})
+        .await
+        .map_err(|e| e.to_string())?;
+
+    app.restart();
+}
+
 #[tauri::command]
 async fn show_notification(app: tauri::AppHandle, title: String, body: String) -> Result<(), String> {
     app.notification()
@@ -61,12 +322,33 @@ async fn update_tray_tooltip(app: tauri::AppHandle, tooltip: String) -> Result<(
     Ok(())
 }
 
-#[tauri::command]
-async fn show_main_window(app: tauri::AppHandle) -> Result<(), String> {
+/// Shows and focuses the main window. Shared by the `show_main_window` command
+/// and the single-instance callback below.
+fn focus_main_window(app: &tauri::AppHandle) {
     if let Some(window) = app.get_webview_window("main") {
-        window.show().map_err(|e| e.to_string())?;
-        window.set_focus().map_err(|e| e.to_string())?;
+        let _ = window.show();
+        let _ = window.set_focus();
     }
+}
+
+/// Keeps the tray's "Show/Hide HiroTrack" item's label in sync with actual window
+/// visibility, since it's a single toggle rather than separate show/hide entries.
+fn sync_toggle_visibility_label(app: &tauri::AppHandle, visible: bool) {
+    let label = if visible { "Hide HiroTrack" } else { "Show HiroTrack" };
+    if let Some(tray) = app.tray_by_id("main") {
+        if let Some(menu) = tray.menu() {
+            if let Some(item) = menu.get("toggle_visibility") {
+                if let Some(menu_item) = item.as_menuitem() {
+                    let _ = menu_item.set_text(label);
+                }
+            }
+        }
+    }
+}
+
+#[tauri::command]
+async fn show_main_window(app: tauri::AppHandle) -> Result<(), String> {
+    focus_main_window(&app);
     Ok(())
 }
 
@@ -74,6 +356,7 @@ async fn show_main_window(app: tauri::AppHandle) -> Result<(), String> {
 async fn hide_main_window(app: tauri::AppHandle) -> Result<(), String> {
     if let Some(window) = app.get_webview_window("main") {
         window.hide().map_err(|e| e.to_string())?;
+        sync_toggle_visibility_label(&app, false);
     }
     Ok(())
 }
@@ -81,16 +364,68 @@ async fn hide_main_window(app: tauri::AppHandle) -> Result<(), String> {
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
+        .plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
+            // A second launch (e.g. autostart plus a manual open) lands here instead of
+            // spawning its own tray icon; just bring the existing window forward.
+            focus_main_window(app);
+        }))
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_autostart::init(
+            tauri_plugin_autostart::MacosLauncher::LaunchAgent,
+            Some(vec![]),
+        ))
         .plugin(tauri_plugin_store::Builder::new().build())
         .plugin(tauri_plugin_notification::init())
+        .plugin(
+            tauri_plugin_global_shortcut::Builder::new()
+                .with_handler(|app, _shortcut, event| {
+                    if event.state == ShortcutState::Pressed {
+                        let timer_state = app.state::<TimerState>();
+                        let now_running = !timer_state.running.fetch_xor(true, Ordering::Relaxed);
+                        let body = if now_running { "Timer started" } else { "Timer stopped" };
+
+                        let _ = app.emit("toggle-timer", ());
+                        let _ = app.notification().builder().title("HiroTrack").body(body).show();
+                    }
+                })
+                .build(),
+        )
+        .plugin(tauri_plugin_updater::Builder::new().build())
+        .manage(IdleState::default())
+        .manage(UpdateState::default())
+        .manage(kimai::KimaiClientState::default())
+        .manage(crypto::DerivedKeyCache::default())
+        .manage(TimerState::default())
         .setup(|app| {
             // Create system tray menu
-            let show_item = MenuItem::with_id(app, "show", "Show HiroTrack", true, None::<&str>)?;
-            let hide_item = MenuItem::with_id(app, "hide", "Hide to Tray", true, None::<&str>)?;
+
+            // Reconcile the persisted autostart preference with the actual OS entry
+            // (e.g. it can go missing if the app was moved) before reading it back
+            // for the checkbox's initial state.
+            if let Ok(store) = tauri_plugin_store::StoreBuilder::new(app, "settings.json").build() {
+                if let Some(pref) = store.get("autostart").and_then(|v| v.as_bool()) {
+                    let manager = app.autolaunch();
+                    if pref {
+                        let _ = manager.enable();
+                    } else {
+                        let _ = manager.disable();
+                    }
+                }
+            }
+
+            let toggle_visibility_item =
+                MenuItem::with_id(app, "toggle_visibility", "Hide HiroTrack", true, None::<&str>)?;
+            let autostart_item = CheckMenuItem::with_id(
+                app,
+                "autostart",
+                "Start at login",
+                true,
+                app.autolaunch().is_enabled().unwrap_or(false),
+                None::<&str>,
+            )?;
             let quit_item = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
-            
-            let menu = Menu::with_items(app, &[&show_item, &hide_item, &quit_item])?;
+
+            let menu = Menu::with_items(app, &[&toggle_visibility_item, &autostart_item, &quit_item])?;
             
             // Create system tray
             let _tray = TrayIconBuilder::with_id("main")
@@ -105,13 +440,17 @@ pub fn run() {
                             ..
                         } => {
                             // Show/hide window on left click
-                            if let Some(window) = tray.app_handle().get_webview_window("main") {
-                                if window.is_visible().unwrap_or(false) {
+                            let app = tray.app_handle();
+                            if let Some(window) = app.get_webview_window("main") {
+                                let now_visible = if window.is_visible().unwrap_or(false) {
                                     let _ = window.hide();
+                                    false
                                 } else {
                                     let _ = window.show();
                                     let _ = window.set_focus();
-                                }
+                                    true
+                                };
+                                sync_toggle_visibility_label(app, now_visible);
                             }
                         }
                         _ => {}
@@ -119,16 +458,34 @@ pub fn run() {
                 })
                 .on_menu_event(|app, event| {
                     match event.id().as_ref() {
-                        "show" => {
+                        "toggle_visibility" => {
                             if let Some(window) = app.get_webview_window("main") {
-                                let _ = window.show();
-                                let _ = window.set_focus();
+                                let now_visible = if window.is_visible().unwrap_or(false) {
+                                    let _ = window.hide();
+                                    false
+                                } else {
+                                    let _ = window.show();
+                                    let _ = window.set_focus();
+                                    true
+                                };
+                                sync_toggle_visibility_label(app, now_visible);
                             }
                         }
-                        "hide" => {
-                            if let Some(window) = app.get_webview_window("main") {
-                                let _ = window.hide();
-                            }
+                        "autostart" => {
+                            // Route through the same command the frontend calls, so the
+                            // tray and the settings UI can't disagree about whether the
+                            // preference was actually persisted.
+                            let enabled = app.autolaunch().is_enabled().unwrap_or(false);
+                            let app = app.clone();
+                            tauri::async_runtime::spawn(async move {
+                                let _ = set_autostart(app, !enabled).await;
+                            });
+                        }
+                        "install_update" => {
+                            let app = app.clone();
+                            tauri::async_runtime::spawn(async move {
+                                let _ = install_update(app).await;
+                            });
                         }
                         "quit" => {
                             app.exit(0);
@@ -137,7 +494,50 @@ pub fn run() {
                     }
                 })
                 .build(app)?;
-            
+
+            // Keep the tray toggle label accurate even when visibility changes without
+            // going through the tray at all (e.g. the window is hidden on close).
+            if let Some(window) = app.get_webview_window("main") {
+                let app_handle = app.handle().clone();
+                let event_window = window.clone();
+                window.on_window_event(move |event| {
+                    if matches!(event, tauri::WindowEvent::Focused(_)) {
+                        let visible = event_window.is_visible().unwrap_or(false);
+                        sync_toggle_visibility_label(&app_handle, visible);
+                    }
+                });
+            }
+
+            // Restore the persisted idle-detection preferences before starting the poller.
+            if let Ok(store) = tauri_plugin_store::StoreBuilder::new(app, "settings.json").build() {
+                let idle_state = app.state::<IdleState>();
+                if let Some(threshold) = store.get("idle_threshold_secs").and_then(|v| v.as_u64()) {
+                    idle_state.threshold_secs.store(threshold, Ordering::Relaxed);
+                }
+                if let Some(enabled) = store.get("idle_detection_enabled").and_then(|v| v.as_bool()) {
+                    idle_state.enabled.store(enabled, Ordering::Relaxed);
+                }
+            }
+            idle::spawn_idle_monitor(app.handle().clone());
+
+            // Re-register the saved global hotkey so it survives restarts.
+            if let Ok(store) = tauri_plugin_store::StoreBuilder::new(app, "settings.json").build() {
+                if let Some(accelerator) = store.get("toggle_hotkey").and_then(|v| v.as_str().map(|s| s.to_string())) {
+                    let _ = register_toggle_hotkey(&app.handle().clone(), &accelerator);
+                }
+            }
+
+            // Silently check for updates on startup, unless the user opted out.
+            let auto_check_updates = tauri_plugin_store::StoreBuilder::new(app, "settings.json")
+                .build()
+                .ok()
+                .and_then(|store| store.get("auto_check_updates").and_then(|v| v.as_bool()))
+                .unwrap_or(true);
+            if auto_check_updates {
+                let app_handle = app.handle().clone();
+                tauri::async_runtime::spawn(updater::check_silently(app_handle));
+            }
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -145,6 +545,19 @@ pub fn run() {
             save_credentials,
             load_credentials,
             clear_credentials,
+            set_autostart,
+            get_autostart,
+            set_idle_threshold,
+            set_idle_detection,
+            set_toggle_hotkey,
+            clear_toggle_hotkey,
+            set_timer_running,
+            kimai_request,
+            set_proxy,
+            set_custom_ca,
+            set_auto_check_updates,
+            check_for_updates,
+            install_update,
             show_notification,
             update_tray_tooltip,
             show_main_window,
@@ -0,0 +1,52 @@
+//! Wraps `tauri-plugin-updater` so a pending update can be checked for once and then
+//! installed later from either the tray or the `install_update` command.
+
+use std::sync::Mutex;
+
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+use tauri_plugin_updater::{Update, UpdaterExt};
+
+/// Holds the update found by the last `check()`, if any, so `install_update` doesn't
+/// have to re-query the release endpoint.
+#[derive(Default)]
+pub struct UpdateState(pub Mutex<Option<Update>>);
+
+/// Runs a silent check and, if an update is available, stashes it in `UpdateState`,
+/// shows a notification, and adds the "Install update & restart" tray item.
+pub async fn check_silently(app: AppHandle) {
+    let updater = match app.updater() {
+        Ok(updater) => updater,
+        Err(_) => return,
+    };
+
+    let update = match updater.check().await {
+        Ok(Some(update)) => update,
+        _ => return,
+    };
+
+    let version = update.version.clone();
+    *app.state::<UpdateState>().0.lock().unwrap() = Some(update);
+
+    let _ = app
+        .notification()
+        .builder()
+        .title("HiroTrack update available")
+        .body(format!("Version {version} is ready to install."))
+        .show();
+
+    add_install_menu_item(&app);
+}
+
+fn add_install_menu_item(app: &AppHandle) {
+    use tauri::menu::MenuItem;
+
+    let Some(tray) = app.tray_by_id("main") else { return };
+    let Some(menu) = tray.menu() else { return };
+    if menu.get("install_update").is_some() {
+        return;
+    }
+    if let Ok(item) = MenuItem::with_id(app, "install_update", "Install update & restart", true, None::<&str>) {
+        let _ = menu.append(&item);
+    }
+}